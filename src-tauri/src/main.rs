@@ -1,57 +1,45 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use tauri::{Manager, Runtime};
-use tauri_plugin_updater::UpdaterExt;
+use tauri::Runtime;
 
-#[derive(Clone, serde::Serialize)]
-struct UpdatePayload {
-    message: String,
-    version: String,
+mod updater;
+
+use updater::{run_update_flow, UpdateFlowMode};
+
+// Command to switch the release channel (stable/beta/nightly) the updater queries
+#[tauri::command]
+async fn set_update_channel<R: Runtime>(app: tauri::AppHandle<R>, channel: String) -> Result<(), String> {
+    updater::set_channel(&app, channel).await
+}
+
+// Command to ramp or pin the staged-rollout policy without shipping a new binary
+#[tauri::command]
+async fn set_rollout_policy<R: Runtime>(
+    app: tauri::AppHandle<R>,
+    rollout_percent: Option<u8>,
+    minimum_version: Option<String>,
+) -> Result<(), String> {
+    updater::set_rollout_policy(&app, rollout_percent, minimum_version).await
 }
 
 // Custom command to check for updates manually
 #[tauri::command]
 async fn check_for_updates<R: Runtime>(app: tauri::AppHandle<R>) -> Result<String, String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
-    
-    match updater.check().await {
-        Ok(Some(update)) => {
-            let version = update.version.clone();
-            let body = update.body.clone().unwrap_or_default();
-            
-            // Emit update available event
-            app.emit("update-available", UpdatePayload {
-                message: body,
-                version: version.clone(),
-            }).ok();
-            
-            Ok(format!("Update available: {}", version))
-        }
+    match run_update_flow(&app, UpdateFlowMode::Prompted).await {
+        Ok(Some(update)) => Ok(format!("Update available: {}", update.version)),
         Ok(None) => Ok("No updates available".to_string()),
-        Err(e) => Err(e.to_string())
+        Err(e) => Err(e),
     }
 }
 
 // Command to trigger update download and installation
 #[tauri::command]
 async fn download_and_install_update<R: Runtime>(app: tauri::AppHandle<R>) -> Result<String, String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
-    
-    match updater.check().await {
-        Ok(Some(update)) => {
-            // Emit download started event
-            app.emit("update-download-started", ()).ok();
-            
-            // Download and install
-            update.download_and_install(|_event, _data| {
-                // Progress callback - can emit progress events here
-            }).await.map_err(|e| e.to_string())?;
-            
-            Ok("Update downloaded and installed successfully".to_string())
-        }
+    match run_update_flow(&app, UpdateFlowMode::Forced).await {
+        Ok(Some(_)) => Ok("Update downloaded and installed successfully".to_string()),
         Ok(None) => Ok("No updates available".to_string()),
-        Err(e) => Err(e.to_string())
+        Err(e) => Err(e),
     }
 }
 
@@ -80,29 +68,17 @@ fn main() {
             tauri::async_runtime::spawn(async move {
                 // Wait a bit before checking for updates
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                
-                if let Ok(updater) = app_handle.updater() {
-                    match updater.check().await {
-                        Ok(Some(update)) => {
-                            let version = update.version.clone();
-                            let body = update.body.clone().unwrap_or_default();
-                            
-                            // Emit update available event
-                            app_handle.emit("update-available", UpdatePayload {
-                                message: body,
-                                version,
-                            }).ok();
-                        }
-                        _ => {}
-                    }
-                }
+
+                let _ = run_update_flow(&app_handle, UpdateFlowMode::Prompted).await;
             });
-            
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             check_for_updates,
             download_and_install_update,
+            set_update_channel,
+            set_rollout_policy,
             open_external
         ])
         .run(tauri::generate_context!())
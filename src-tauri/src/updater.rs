@@ -0,0 +1,456 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{Manager, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_updater::{Update, UpdaterExt, Url};
+
+/// How long a "remind me later" deferral lasts before we ask again.
+const REMIND_LATER_HOURS: u64 = 24;
+
+/// Release channels the updater can be pointed at. The server templates
+/// `{{target}}`/`{{current_version}}` (substituted by the updater plugin itself)
+/// alongside the channel segment we fill in here.
+const VALID_CHANNELS: [&str; 3] = ["stable", "beta", "nightly"];
+const DEFAULT_CHANNEL: &str = "stable";
+const UPDATE_ENDPOINT_TEMPLATE: &str = "https://updates.toolslab.app/{channel}/{{target}}/{{current_version}}";
+
+/// Default rollout percentage when no policy has been persisted yet: ship to
+/// everyone until something (a server response or an explicit call) says
+/// otherwise.
+const DEFAULT_ROLLOUT_PERCENT: u8 = 100;
+
+/// Naive `major.minor.patch...` comparison so we don't need a semver dependency
+/// just for a minimum-version gate. Non-numeric components are treated as 0.
+/// Versions with fewer segments are zero-padded so e.g. "1.2" == "1.2.0".
+fn version_less_than(a: &str, b: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    };
+    let (mut a, mut b) = (parse(a), parse(b));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a < b
+}
+
+/// Generates a new random per-install identifier used to bucket clients for
+/// staged rollouts. Not cryptographically random, just unique enough that two
+/// installs don't collide into the same bucket.
+fn generate_installation_id() -> String {
+    use std::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut hasher = DefaultHasher::new();
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().hash(&mut hasher);
+    std::process::id().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Returns this install's persisted rollout-bucketing identifier, generating
+/// and saving one on first use. Unlike deriving it from the app config
+/// directory, this doesn't collide across machines that share an app id and
+/// OS username (default/shared accounts, CI runners, kiosk images).
+async fn resolve_installation_id<R: Runtime>(app: &tauri::AppHandle<R>, preferences: &mut UpdatePreferences) -> String {
+    if let Some(id) = &preferences.installation_id {
+        return id.clone();
+    }
+
+    let id = generate_installation_id();
+    preferences.installation_id = Some(id.clone());
+    save_preferences(app, preferences.clone()).await;
+    id
+}
+
+/// Deterministic bucket in `0..100` for this install + candidate version.
+fn rollout_bucket(installation_id: &str, candidate_version: &str) -> u8 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    installation_id.hash(&mut hasher);
+    candidate_version.hash(&mut hasher);
+    (hasher.finish() % 100) as u8
+}
+
+/// Decides whether `update` should be installed on top of the updater's own
+/// "is this newer" check, applying forced minimum-version upgrades and
+/// percentage-based staged rollouts.
+async fn should_install<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    current_version: &str,
+    update: &Update,
+    preferences: &mut UpdatePreferences,
+) -> bool {
+    if let Some(minimum) = preferences.minimum_version.clone() {
+        if version_less_than(current_version, &minimum) {
+            return true;
+        }
+    }
+
+    let rollout_percent = preferences.rollout_percent.unwrap_or(DEFAULT_ROLLOUT_PERCENT);
+    if rollout_percent >= 100 {
+        return true;
+    }
+
+    let id = resolve_installation_id(app, preferences).await;
+    rollout_bucket(&id, &update.version) < rollout_percent
+}
+
+/// The user's standing choices about update prompts, persisted to disk so
+/// the startup check doesn't nag on every launch.
+#[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
+struct UpdatePreferences {
+    /// A version the user explicitly chose to skip; never prompt for it again.
+    skipped_version: Option<String>,
+    /// Unix timestamp (seconds) before which we shouldn't prompt again, set
+    /// when the user picks "Later".
+    remind_later_until: Option<u64>,
+    /// The release channel to query, e.g. "stable", "beta", "nightly".
+    channel: Option<String>,
+    /// Persisted per-install identifier used to bucket staged rollouts.
+    installation_id: Option<String>,
+    /// Percentage (0-100) of installations that should receive updates.
+    /// `None` falls back to [`DEFAULT_ROLLOUT_PERCENT`]. Settable at runtime
+    /// (e.g. from a server response) so a rollout can be ramped without
+    /// shipping a new binary.
+    rollout_percent: Option<u8>,
+    /// Versions strictly older than this are force-upgraded, bypassing the
+    /// rollout percentage, so laggards can't get stuck below a security fix.
+    minimum_version: Option<String>,
+}
+
+fn preferences_path<R: Runtime>(app: &tauri::AppHandle<R>) -> Option<std::path::PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("update-preferences.json"))
+}
+
+/// Reads the persisted preferences off the async executor via `spawn_blocking`,
+/// since this is plain synchronous file I/O.
+async fn load_preferences<R: Runtime>(app: &tauri::AppHandle<R>) -> UpdatePreferences {
+    let path = preferences_path(app);
+    tokio::task::spawn_blocking(move || {
+        path.and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Writes the persisted preferences off the async executor via `spawn_blocking`.
+async fn save_preferences<R: Runtime>(app: &tauri::AppHandle<R>, preferences: UpdatePreferences) {
+    let path = preferences_path(app);
+    let _ = tokio::task::spawn_blocking(move || {
+        let Some(path) = path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(contents) = serde_json::to_string(&preferences) {
+            let _ = std::fs::write(path, contents);
+        }
+    })
+    .await;
+}
+
+/// Persists the release channel the updater should query from now on.
+pub async fn set_channel<R: Runtime>(app: &tauri::AppHandle<R>, channel: String) -> Result<(), String> {
+    if !VALID_CHANNELS.contains(&channel.as_str()) {
+        return Err(format!("unknown update channel: {channel}"));
+    }
+
+    let mut preferences = load_preferences(app).await;
+    preferences.channel = Some(channel);
+    save_preferences(app, preferences).await;
+    Ok(())
+}
+
+/// Persists the staged-rollout policy (percentage and/or forced minimum
+/// version) so it can be ramped at runtime without shipping a new binary.
+/// Pass `None` for a field to leave it unchanged.
+pub async fn set_rollout_policy<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    rollout_percent: Option<u8>,
+    minimum_version: Option<String>,
+) -> Result<(), String> {
+    if rollout_percent.is_some_and(|percent| percent > 100) {
+        return Err("rollout_percent must be between 0 and 100".to_string());
+    }
+
+    let mut preferences = load_preferences(app).await;
+    if let Some(percent) = rollout_percent {
+        preferences.rollout_percent = Some(percent);
+    }
+    if let Some(minimum) = minimum_version {
+        preferences.minimum_version = Some(minimum);
+    }
+    save_preferences(app, preferences).await;
+    Ok(())
+}
+
+fn endpoint_for_channel(channel: &str) -> Result<Url, String> {
+    let url = UPDATE_ENDPOINT_TEMPLATE.replace("{channel}", channel);
+    Url::parse(&url).map_err(|e| e.to_string())
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// True when the user already chose to skip this exact version, or is still
+/// inside a "remind me later" backoff window.
+fn is_deferred(preferences: &UpdatePreferences, candidate_version: &str) -> bool {
+    if preferences.skipped_version.as_deref() == Some(candidate_version) {
+        return true;
+    }
+    preferences.remind_later_until.is_some_and(|until| now_unix() < until)
+}
+
+/// Shows a native "Update available" dialog and returns whether the user
+/// chose to install now.
+async fn ask_install_now<R: Runtime>(app: &tauri::AppHandle<R>, version: &str, body: &str) -> bool {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(body)
+        .title(format!("Update available: {version}"))
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom("Install".into(), "Later".into()))
+        .show(move |install_now| {
+            let _ = tx.send(install_now);
+        });
+    rx.await.unwrap_or(false)
+}
+
+/// Shown after the user declines to install right away; returns whether they
+/// chose to permanently skip the version rather than just defer it.
+async fn ask_skip_version<R: Runtime>(app: &tauri::AppHandle<R>, version: &str) -> bool {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(format!("Skip version {version} and stop reminding you about it?"))
+        .title("Skip this update?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Skip".into(), "Remind Me Later".into()))
+        .show(move |skip| {
+            let _ = tx.send(skip);
+        });
+    rx.await.unwrap_or(false)
+}
+
+/// Lifecycle states of a single update check/install pass. Emitted on the
+/// `update-status` event so the frontend can drive its UI off one channel
+/// instead of juggling several ad-hoc events.
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "state", content = "data")]
+pub enum UpdaterStatus {
+    Checking,
+    UpToDate,
+    Available,
+    Downloading { downloaded: u64, content_length: Option<u64> },
+    Downloaded,
+    Installing,
+    Done,
+    Error(String),
+}
+
+#[derive(Clone, serde::Serialize)]
+struct UpdateStatusEvent {
+    #[serde(flatten)]
+    status: UpdaterStatus,
+    version: Option<String>,
+    body: Option<String>,
+}
+
+fn emit_status<R: Runtime>(app: &tauri::AppHandle<R>, status: UpdaterStatus, version: Option<String>, body: Option<String>) {
+    app.emit("update-status", UpdateStatusEvent { status, version, body }).ok();
+}
+
+/// How a [`run_update_flow`] pass should decide whether to actually install
+/// an update it finds.
+pub enum UpdateFlowMode {
+    /// Ask the user via a native dialog before downloading; honors any prior
+    /// "skip this version" / "remind me later" choice instead of re-prompting.
+    Prompted,
+    /// Download and install immediately, bypassing the dialog.
+    Forced,
+}
+
+/// Whether a `Downloading` progress event should be emitted for this chunk,
+/// throttled so we don't flood the webview. When `percent` is unknown (the
+/// server didn't send a `Content-Length`), percent-based throttling can't
+/// apply, so emission falls back to the elapsed-time check alone.
+fn should_emit_progress(percent: Option<u8>, last_percent: u8, elapsed_ms: u128) -> bool {
+    let percent_advanced = percent.is_some_and(|p| p >= last_percent.saturating_add(1));
+    let time_elapsed = elapsed_ms >= 100;
+    percent_advanced || time_elapsed
+}
+
+/// Drives a full check (and, depending on `mode`, download/install) pass,
+/// emitting one `update-status` transition at a time. Returns the update
+/// metadata when one was found (whether or not it ended up installed, so also
+/// when the user picks "Later" or "Skip" in this pass's dialog), or `None`
+/// when the client is up to date, the candidate was excluded by
+/// [`should_install`], or (in `Prompted` mode) an earlier skip or
+/// "remind me later" choice still applies to it.
+pub async fn run_update_flow<R: Runtime>(app: &tauri::AppHandle<R>, mode: UpdateFlowMode) -> Result<Option<Update>, String> {
+    emit_status(app, UpdaterStatus::Checking, None, None);
+
+    let mut preferences = load_preferences(app).await;
+
+    let endpoint = endpoint_for_channel(preferences.channel.as_deref().unwrap_or(DEFAULT_CHANNEL))?;
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|e| e.to_string())?
+        .build()
+        .map_err(|e| e.to_string())?;
+    let update = match updater.check().await {
+        Ok(Some(update)) => update,
+        Ok(None) => {
+            emit_status(app, UpdaterStatus::UpToDate, None, None);
+            return Ok(None);
+        }
+        Err(e) => {
+            let message = e.to_string();
+            emit_status(app, UpdaterStatus::Error(message.clone()), None, None);
+            return Err(message);
+        }
+    };
+
+    let current_version = app.package_info().version.to_string();
+    if !should_install(app, &current_version, &update, &mut preferences).await {
+        emit_status(app, UpdaterStatus::UpToDate, None, None);
+        return Ok(None);
+    }
+
+    if matches!(mode, UpdateFlowMode::Prompted) && is_deferred(&preferences, &update.version) {
+        emit_status(app, UpdaterStatus::UpToDate, None, None);
+        return Ok(None);
+    }
+
+    emit_status(app, UpdaterStatus::Available, Some(update.version.clone()), update.body.clone());
+
+    if matches!(mode, UpdateFlowMode::Prompted) {
+        let body = update.body.clone().unwrap_or_default();
+        if !ask_install_now(app, &update.version, &body).await {
+            if ask_skip_version(app, &update.version).await {
+                preferences.skipped_version = Some(update.version.clone());
+                preferences.remind_later_until = None;
+            } else {
+                preferences.remind_later_until = Some(now_unix() + REMIND_LATER_HOURS * 3600);
+            }
+            save_preferences(app, preferences).await;
+            return Ok(Some(update));
+        }
+    }
+
+    let mut downloaded: u64 = 0;
+    let mut last_percent: u8 = 0;
+    let mut last_emit = std::time::Instant::now();
+    let progress_app = app.clone();
+    let finished_app = app.clone();
+
+    let install_result = update
+        .download_and_install(
+            move |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                let percent = content_length.map(|total| ((downloaded as f64 / total as f64) * 100.0) as u8);
+
+                if should_emit_progress(percent, last_percent, last_emit.elapsed().as_millis()) {
+                    emit_status(&progress_app, UpdaterStatus::Downloading { downloaded, content_length }, None, None);
+
+                    if let Some(p) = percent {
+                        last_percent = p;
+                    }
+                    last_emit = std::time::Instant::now();
+                }
+            },
+            move || {
+                // Bytes are on disk; the installer runs immediately after this returns.
+                emit_status(&finished_app, UpdaterStatus::Downloaded, None, None);
+                emit_status(&finished_app, UpdaterStatus::Installing, None, None);
+            },
+        )
+        .await;
+
+    match install_result {
+        Ok(_) => {
+            emit_status(app, UpdaterStatus::Done, None, None);
+            Ok(Some(update))
+        }
+        Err(e) => {
+            let message = e.to_string();
+            emit_status(app, UpdaterStatus::Error(message.clone()), None, None);
+            Err(message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttles_by_time_alone_when_content_length_is_unknown() {
+        // No `percent` available, so only the elapsed-time check can gate emission.
+        assert!(!should_emit_progress(None, 0, 0));
+        assert!(!should_emit_progress(None, 0, 99));
+        assert!(should_emit_progress(None, 0, 100));
+    }
+
+    #[test]
+    fn emits_when_percent_advances_even_before_the_time_threshold() {
+        assert!(should_emit_progress(Some(1), 0, 0));
+        assert!(!should_emit_progress(Some(0), 0, 0));
+    }
+
+    #[test]
+    fn version_less_than_zero_pads_shorter_segment_lists() {
+        assert!(!version_less_than("1.2", "1.2.0"));
+        assert!(!version_less_than("1.2.0", "1.2"));
+        assert!(version_less_than("1.2", "1.2.1"));
+    }
+
+    #[test]
+    fn version_less_than_compares_numerically_not_lexically() {
+        assert!(!version_less_than("10.0", "2.0"));
+        assert!(version_less_than("2.0", "10.0"));
+    }
+
+    #[test]
+    fn rollout_bucket_is_always_in_range() {
+        for version in ["1.0.0", "2.0.0", "nightly-2026-07-27"] {
+            let bucket = rollout_bucket("some-install-id", version);
+            assert!(bucket < 100);
+        }
+    }
+
+    #[test]
+    fn rollout_bucket_is_deterministic() {
+        assert_eq!(rollout_bucket("install-a", "1.2.3"), rollout_bucket("install-a", "1.2.3"));
+    }
+
+    #[test]
+    fn is_deferred_honors_skipped_version() {
+        let preferences = UpdatePreferences {
+            skipped_version: Some("1.2.3".to_string()),
+            ..Default::default()
+        };
+        assert!(is_deferred(&preferences, "1.2.3"));
+        assert!(!is_deferred(&preferences, "1.2.4"));
+    }
+
+    #[test]
+    fn is_deferred_honors_remind_later_window() {
+        let future = UpdatePreferences {
+            remind_later_until: Some(now_unix() + 3600),
+            ..Default::default()
+        };
+        assert!(is_deferred(&future, "1.2.3"));
+
+        let past = UpdatePreferences {
+            remind_later_until: Some(now_unix().saturating_sub(1)),
+            ..Default::default()
+        };
+        assert!(!is_deferred(&past, "1.2.3"));
+    }
+}